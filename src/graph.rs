@@ -2,13 +2,30 @@ use serde::Deserialize;
 
 use indexmap::IndexMap;
 use std::cmp::Ord;
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-enum Direction {
+/// Adjacency by plain node index, used internally for graph algorithms that don't need the full
+/// `Connection` struct (just "which nodes are reachable in one hop").
+type Adjacency = Vec<Vec<usize>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
     LeftRight,
     TopDown,
 }
 
+impl Direction {
+    fn parse(value: &str) -> Result<Direction, GraphError> {
+        match value {
+            "LR" => Ok(Direction::LeftRight),
+            "TD" => Ok(Direction::TopDown),
+            other => Err(GraphError::ParseError {
+                message: format!("unknown layout direction '{other}', expected 'LR' or 'TD'"),
+            }),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct GraphFormat {
@@ -31,7 +48,7 @@ struct ConnectionFormat {
     to:   String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Node {
     pub id:   String,
     pub name: String,
@@ -42,6 +59,10 @@ pub struct Node {
 pub struct Connection {
     pub from_index: usize,
     pub to_index:   usize,
+    /// Position of this connection within `from_connections[from_index]`, counted over all of
+    /// the source node's outgoing edges. Being unique per source node, it lets `VirtualChain`s
+    /// disambiguate duplicate `(from_index, to_index)` pairs.
+    pub source_edge_index: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +70,7 @@ pub struct Graph {
     nodes:            Vec<Node>,
     from_connections: Vec<Vec<Connection>>,
     to_connections:   Vec<Vec<Connection>>,
+    direction:        Direction,
 }
 
 #[derive(Debug)]
@@ -62,16 +84,45 @@ pub struct SortOrder {
     pub depths:         Vec<usize>,
     pub index_at_depth: Vec<usize>,
     pub nodes_in_level: Vec<usize>,
+    pub virtual_chains: Vec<VirtualChain>,
+    /// Original `(from_index, to_index)` pairs whose edge was reversed to break a cycle, plus
+    /// self-loops (which are left out of layering); `to_svg` draws these with a distinct style
+    /// since their layout direction runs against the data flow.
+    pub reversed_edges: BTreeSet<(usize, usize)>,
+}
+
+/// Records how a connection spanning more than one depth level was split into a chain of virtual
+/// nodes, one per intermediate level, so `to_svg` can route its spline through each slot's
+/// position instead of stretching a single bezier through intervening boxes.
+pub struct VirtualChain {
+    pub from_index: usize,
+    pub to_index:   usize,
+    /// Matches the originating `Connection::source_edge_index`, so `to_svg` can tell apart
+    /// several parallel long-range connections between the same pair of nodes.
+    pub source_edge_index: usize,
+    pub depths: Vec<usize>,
+    pub ranks:  Vec<usize>,
 }
 
 impl Graph {
+    /// Parses a graph from either the `GraphFormat` JSON or the Mermaid-style text DSL.
+    /// The format is sniffed by checking whether the content starts with `{`.
     pub fn from_str(content: &str) -> Result<Graph, GraphError> {
+        if content.trim_start().starts_with('{') {
+            Self::from_json(content)
+        } else {
+            Self::from_dsl(content)
+        }
+    }
+
+    fn from_json(content: &str) -> Result<Graph, GraphError> {
         let parsed: GraphFormat = serde_json::from_str(content).map_err(|e| GraphError::ParseError { message: e.to_string() })?;
 
         let mut result = Graph {
             nodes:            Vec::new(),
             from_connections: Vec::with_capacity(parsed.nodes.len()),
             to_connections:   Vec::with_capacity(parsed.nodes.len()),
+            direction:        Direction::parse(&parsed.layout_direction)?,
         };
 
         for _ in 0..parsed.nodes.len() {
@@ -97,17 +148,134 @@ impl Graph {
             let to_index = *index_map.get(&connection.to).ok_or_else(|| GraphError::InternalError {
                 message: format!("Invalid to reference {}", connection.to),
             })?;
-            result.from_connections[from_index].push(Connection { from_index, to_index });
-            result.to_connections[to_index].push(Connection { from_index, to_index });
+            let source_edge_index = result.from_connections[from_index].len();
+            result.from_connections[from_index].push(Connection { from_index, to_index, source_edge_index });
+            result.to_connections[to_index].push(Connection { from_index, to_index, source_edge_index });
+        }
+
+        Ok(result)
+    }
+
+    /// Parses the textual DSL used inside ` ```merman ` fences, e.g.:
+    /// ```text
+    /// direction LR
+    /// a[Input 0] --> c{Add}
+    /// b[Bias] --> c
+    /// ```
+    /// `[...]` declares a plain box (`op: None`), `{...}` declares an op node, and a node
+    /// mentioned without brackets is auto-created as a plain box named after its id.
+    fn from_dsl(content: &str) -> Result<Graph, GraphError> {
+        let mut result = Graph {
+            nodes:            Vec::new(),
+            from_connections: Vec::new(),
+            to_connections:   Vec::new(),
+            direction:        Direction::LeftRight,
+        };
+
+        let mut index_map: BTreeMap<String, usize> = BTreeMap::new();
+
+        for (line_index, raw_line) in content.lines().enumerate() {
+            let line_number = line_index + 1;
+            let trimmed = raw_line.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let mut words = trimmed.splitn(2, char::is_whitespace);
+            if words.next() == Some("direction") {
+                let direction_value = words.next().map(str::trim).unwrap_or("");
+                result.direction = Direction::parse(direction_value).map_err(|_| GraphError::ParseError {
+                    message: format!(
+                        "expected 'LR' or 'TD' after 'direction', found '{direction_value}' at line {line_number} column 1"
+                    ),
+                })?;
+                continue;
+            }
+
+            let arrow_index = raw_line.find("-->").ok_or_else(|| GraphError::ParseError {
+                message: format!("expected '-->' in connection at line {line_number} column 1"),
+            })?;
+
+            let from_part = &raw_line[..arrow_index];
+            let to_part = &raw_line[arrow_index + "-->".len()..];
+
+            let from_column = from_part.len() - from_part.trim_start().len() + 1;
+            let to_column = arrow_index + "-->".len() + (to_part.len() - to_part.trim_start().len()) + 1;
+
+            let from_index = Self::parse_dsl_node_ref(&mut result, &mut index_map, from_part.trim(), line_number, from_column)?;
+            let to_index = Self::parse_dsl_node_ref(&mut result, &mut index_map, to_part.trim(), line_number, to_column)?;
+
+            let source_edge_index = result.from_connections[from_index].len();
+            result.from_connections[from_index].push(Connection { from_index, to_index, source_edge_index });
+            result.to_connections[to_index].push(Connection { from_index, to_index, source_edge_index });
+        }
+
+        if result.nodes.is_empty() {
+            return Err(GraphError::ParseError {
+                message: "no nodes or connections found".into(),
+            });
         }
 
         Ok(result)
     }
 
+    /// Parses a single `id`, `id[label]` or `id{label}` reference, auto-creating the node on
+    /// first mention, and returns its index.
+    fn parse_dsl_node_ref(
+        graph: &mut Graph,
+        index_map: &mut BTreeMap<String, usize>,
+        text: &str,
+        line_number: usize,
+        column: usize,
+    ) -> Result<usize, GraphError> {
+        let (id, label) = match text.find(['[', '{']) {
+            Some(bracket_index) => {
+                let id = text[..bracket_index].trim();
+                let is_op = text.as_bytes()[bracket_index] == b'{';
+                let close = if is_op { '}' } else { ']' };
+                let close_index = text.rfind(close).ok_or_else(|| GraphError::ParseError {
+                    message: format!("unterminated node label '{text}' at line {line_number} column {column}"),
+                })?;
+                (id, Some((is_op, text[bracket_index + 1..close_index].trim().to_string())))
+            }
+            None => (text, None),
+        };
+
+        if id.is_empty() {
+            return Err(GraphError::ParseError {
+                message: format!("expected a node identifier at line {line_number} column {column}"),
+            });
+        }
+
+        let index = *index_map.entry(id.to_string()).or_insert_with(|| {
+            graph.nodes.push(Node {
+                id:   id.to_string(),
+                name: id.to_string(),
+                op:   None,
+            });
+            graph.from_connections.push(Vec::new());
+            graph.to_connections.push(Vec::new());
+            graph.nodes.len() - 1
+        });
+
+        if let Some((is_op, label_text)) = label {
+            let node = &mut graph.nodes[index];
+            node.name = label_text.clone();
+            node.op = if is_op { Some(label_text) } else { None };
+        }
+
+        Ok(index)
+    }
+
     pub fn node_size(&self) -> usize {
         self.nodes.len()
     }
 
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
     pub fn node(&self, node_index: usize) -> &Node {
         &self.nodes[node_index]
     }
@@ -117,25 +285,24 @@ impl Graph {
     }
 
     pub fn reverse_topological_sort(&self) -> Result<SortOrder, GraphError> {
-        // Push nodes which do not have outgoing connections
-        let mut nodes_to_visit: VecDeque<(usize, usize)> = self
-            .from_connections
-            .iter()
-            .enumerate()
-            .filter(|(_, from_connections)| from_connections.is_empty())
-            .map(|(i, _)| (i, 0usize))
-            .collect();
-
-        if nodes_to_visit.is_empty() {
+        if self.node_size() == 0 {
             return Err(GraphError::InternalError {
                 message: "EmptyGraph".into(),
             });
         }
 
+        // Cycles are broken by reversing a greedily-computed feedback arc set before layering, so
+        // recurrent graphs (RNNs, residual connections, ...) still render instead of erroring out.
+        let (effective_out, effective_in, reversed_edges) = self.break_cycles();
+
+        let mut nodes_to_visit: VecDeque<(usize, usize)> =
+            (0..self.node_size()).filter(|&node_index| effective_out[node_index].is_empty()).map(|node_index| (node_index, 0usize)).collect();
+
         let mut nodes_distance: Vec<usize> = vec![usize::MAX; self.node_size()];
 
         while let Some((current_node_index, current_node_distance)) = nodes_to_visit.pop_front() {
-            // Cycle detection
+            // Reversing the feedback arc set makes `effective_out`/`effective_in` acyclic, so this
+            // is an invariant check rather than an expected case.
             if current_node_distance >= self.node_size() {
                 return Err(GraphError::InternalError {
                     message: format!("Cycle detected at node {}", &self.node(current_node_index).name),
@@ -153,8 +320,8 @@ impl Graph {
             let new_node_distance = current_node_distance + 1;
 
             // Add originating nodes to queue
-            for connection in self.to_connections[current_node_index].iter() {
-                nodes_to_visit.push_back((connection.from_index, new_node_distance));
+            for &source_index in effective_in[current_node_index].iter() {
+                nodes_to_visit.push_back((source_index, new_node_distance));
             }
         }
 
@@ -169,31 +336,369 @@ impl Graph {
 
         let num_depth_levels = *depths.last().unwrap() + 1;
 
-        let mut nodes_in_depth_level = vec![0usize; num_depth_levels];
-
-        let mut index_at_depth = vec![0usize; order_indices.len()];
+        // Connections spanning more than one depth level get split into a chain of virtual
+        // nodes, one per intermediate level, so the crossing-minimization pass below can order
+        // them alongside real nodes and `to_svg` can route the edge's spline through open channels.
+        let (slot_depths, adjacency, pending_chains) = self.build_layout_slots(&nodes_distance);
 
-        let mut last_depth_index = 0usize;
-        let mut index_within_depth = 0usize;
+        let levels = minimize_crossings(&slot_depths, num_depth_levels, &adjacency);
 
-        for depth_index in 0..depths.len() {
-            if depths[depth_index] != last_depth_index {
-                last_depth_index = depths[depth_index];
-                index_within_depth = 0usize;
+        let mut rank_within_slot = vec![0usize; slot_depths.len()];
+        for level in &levels {
+            for (rank, &slot) in level.iter().enumerate() {
+                rank_within_slot[slot] = rank;
             }
-            index_at_depth[depth_index] = index_within_depth;
-            nodes_in_depth_level[last_depth_index] = index_within_depth + 1;
-
-            index_within_depth += 1;
         }
 
+        let index_at_depth = order_indices.iter().map(|&node_index| rank_within_slot[node_index]).collect();
+        let nodes_in_level = levels.iter().map(Vec::len).collect();
+
+        let virtual_chains = pending_chains
+            .into_iter()
+            .map(|chain| VirtualChain {
+                from_index:        chain.from_index,
+                to_index:          chain.to_index,
+                source_edge_index: chain.source_edge_index,
+                depths:            chain.slots.iter().map(|&slot| slot_depths[slot]).collect(),
+                ranks:             chain.slots.iter().map(|&slot| rank_within_slot[slot]).collect(),
+            })
+            .collect();
+
         Ok(SortOrder {
             order_indices,
             depths,
             index_at_depth,
-            nodes_in_level: nodes_in_depth_level,
+            nodes_in_level,
+            virtual_chains,
+            reversed_edges,
+        })
+    }
+
+    /// Breaks cycles by computing a greedy feedback arc set (Eades-Lin-Smyth heuristic) and
+    /// reversing those edges. Returns effective outgoing/incoming adjacency (by node index, not
+    /// `Connection`) suitable for layering, plus the set of original `(from_index, to_index)`
+    /// pairs that were reversed to get there (and of self-loops, which are dropped), so `to_svg`
+    /// can render them with a distinct style.
+    fn break_cycles(&self) -> (Adjacency, Adjacency, BTreeSet<(usize, usize)>) {
+        let order = self.feedback_arc_set_order();
+
+        let mut position = vec![0usize; self.node_size()];
+        for (rank, &node_index) in order.iter().enumerate() {
+            position[node_index] = rank;
+        }
+
+        let mut effective_out: Vec<Vec<usize>> = vec![Vec::new(); self.node_size()];
+        let mut effective_in: Vec<Vec<usize>> = vec![Vec::new(); self.node_size()];
+        let mut reversed_edges = BTreeSet::new();
+
+        for (from_index, edges) in self.from_connections.iter().enumerate() {
+            for connection in edges {
+                let to_index = connection.to_index;
+
+                if from_index == to_index {
+                    // A self-loop is neither forward nor backward relative to any order, and
+                    // leaving it in `effective_out`/`effective_in` would make the node look like
+                    // it always has an outgoing (and incoming) edge, so it could never become a
+                    // sink during layering. Drop it from the layering graph entirely and render
+                    // it the same way as a reversed edge.
+                    reversed_edges.insert((from_index, to_index));
+                    continue;
+                }
+
+                if position[from_index] > position[to_index] {
+                    // Backward relative to the chosen order: reverse it so layering sees a DAG.
+                    effective_out[to_index].push(from_index);
+                    effective_in[from_index].push(to_index);
+                    reversed_edges.insert((from_index, to_index));
+                } else {
+                    effective_out[from_index].push(to_index);
+                    effective_in[to_index].push(from_index);
+                }
+            }
+        }
+
+        (effective_out, effective_in, reversed_edges)
+    }
+
+    /// Greedy Eades-Lin-Smyth linear arrangement: repeatedly peel off current sinks (appended to
+    /// a right sequence) and current sources (appended to a left sequence); once neither remains,
+    /// move the vertex maximizing `outdeg - indeg` to the left sequence instead. The final order
+    /// is `left` followed by `right` reversed. Edges that run backward relative to this order are
+    /// exactly the feedback arc set.
+    fn feedback_arc_set_order(&self) -> Vec<usize> {
+        let node_count = self.node_size();
+
+        let out_neighbors: Vec<Vec<usize>> =
+            self.from_connections.iter().map(|edges| edges.iter().map(|connection| connection.to_index).collect()).collect();
+        let in_neighbors: Vec<Vec<usize>> =
+            self.to_connections.iter().map(|edges| edges.iter().map(|connection| connection.from_index).collect()).collect();
+
+        let mut out_degree: Vec<usize> = out_neighbors.iter().map(Vec::len).collect();
+        let mut in_degree: Vec<usize> = in_neighbors.iter().map(Vec::len).collect();
+        let mut removed = vec![false; node_count];
+
+        let mut left = Vec::with_capacity(node_count);
+        let mut right = Vec::with_capacity(node_count);
+        let mut remaining = node_count;
+
+        while remaining > 0 {
+            loop {
+                let sinks: Vec<usize> = (0..node_count).filter(|&node_index| !removed[node_index] && out_degree[node_index] == 0).collect();
+                if sinks.is_empty() {
+                    break;
+                }
+                for node_index in sinks {
+                    removed[node_index] = true;
+                    remaining -= 1;
+                    right.push(node_index);
+                    for &source in &in_neighbors[node_index] {
+                        if !removed[source] {
+                            out_degree[source] -= 1;
+                        }
+                    }
+                }
+            }
+
+            loop {
+                let sources: Vec<usize> = (0..node_count).filter(|&node_index| !removed[node_index] && in_degree[node_index] == 0).collect();
+                if sources.is_empty() {
+                    break;
+                }
+                for node_index in sources {
+                    removed[node_index] = true;
+                    remaining -= 1;
+                    left.push(node_index);
+                    for &target in &out_neighbors[node_index] {
+                        if !removed[target] {
+                            in_degree[target] -= 1;
+                        }
+                    }
+                }
+            }
+
+            if remaining == 0 {
+                break;
+            }
+
+            let best_node = (0..node_count)
+                .filter(|&node_index| !removed[node_index])
+                .max_by_key(|&node_index| out_degree[node_index] as isize - in_degree[node_index] as isize)
+                .unwrap();
+
+            removed[best_node] = true;
+            remaining -= 1;
+            left.push(best_node);
+            for &target in &out_neighbors[best_node] {
+                if !removed[target] {
+                    in_degree[target] -= 1;
+                }
+            }
+            for &source in &in_neighbors[best_node] {
+                if !removed[source] {
+                    out_degree[source] -= 1;
+                }
+            }
+        }
+
+        left.extend(right.into_iter().rev());
+        left
+    }
+
+    /// Builds the layout-slot graph used for crossing minimization and edge routing: every real
+    /// node keeps its own slot (`0..node_size()`), and every connection spanning more than one
+    /// depth level gets one extra virtual slot per intermediate level, chained together with unit
+    /// depth steps. Returns each slot's depth, a symmetric adjacency list over slots restricted to
+    /// depth-adjacent pairs (a self-loop adds the node's own slot to its list), and the virtual
+    /// slot chain recorded for each split connection.
+    fn build_layout_slots(&self, nodes_distance: &[usize]) -> (Vec<usize>, Vec<Vec<usize>>, Vec<PendingVirtualChain>) {
+        let mut slot_depths = nodes_distance.to_vec();
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); self.node_size()];
+        let mut pending_chains = Vec::new();
+
+        for (from_index, edges) in self.from_connections.iter().enumerate() {
+            for connection in edges {
+                let to_index = connection.to_index;
+                let from_depth = nodes_distance[from_index];
+                let to_depth = nodes_distance[to_index];
+                let gap = from_depth.abs_diff(to_depth);
+
+                if gap <= 1 {
+                    adjacency[from_index].push(to_index);
+                    adjacency[to_index].push(from_index);
+                    continue;
+                }
+
+                let step: isize = if to_depth > from_depth { 1 } else { -1 };
+                let mut previous = from_index;
+                let mut slots = Vec::with_capacity(gap - 1);
+
+                for hop in 1..gap {
+                    let slot = slot_depths.len();
+                    slot_depths.push((from_depth as isize + step * hop as isize) as usize);
+                    adjacency.push(Vec::new());
+
+                    adjacency[previous].push(slot);
+                    adjacency[slot].push(previous);
+
+                    slots.push(slot);
+                    previous = slot;
+                }
+
+                adjacency[previous].push(to_index);
+                adjacency[to_index].push(previous);
+
+                pending_chains.push(PendingVirtualChain {
+                    from_index,
+                    to_index,
+                    source_edge_index: connection.source_edge_index,
+                    slots,
+                });
+            }
+        }
+
+        (slot_depths, adjacency, pending_chains)
+    }
+}
+
+struct PendingVirtualChain {
+    from_index:        usize,
+    to_index:          usize,
+    source_edge_index: usize,
+    slots:             Vec<usize>,
+}
+
+/// Barycenter/median layer-ordering pass: reorders slots within each depth level to reduce edge
+/// crossings between adjacent levels via alternating down/up sweeps, keeping whichever ordering
+/// seen across all sweeps minimized total crossings. Returns, for each depth, the slot ids in
+/// their chosen order.
+fn minimize_crossings(slot_depths: &[usize], num_depth_levels: usize, adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const SWEEP_ITERATIONS: usize = 8;
+
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); num_depth_levels];
+    for (slot, &depth) in slot_depths.iter().enumerate() {
+        levels[depth].push(slot);
+    }
+
+    let mut best_levels = levels.clone();
+    let mut best_crossings = count_total_crossings(&levels, adjacency);
+
+    for iteration in 0..SWEEP_ITERATIONS {
+        if iteration % 2 == 0 {
+            // Down sweep: reorder each level by the median position of its neighbors one level up.
+            for depth in 1..num_depth_levels {
+                reorder_level(&mut levels, adjacency, depth, depth - 1);
+            }
+        } else {
+            // Up sweep: reorder each level by the median position of its neighbors one level down.
+            for depth in (0..num_depth_levels.saturating_sub(1)).rev() {
+                reorder_level(&mut levels, adjacency, depth, depth + 1);
+            }
+        }
+
+        let crossings = count_total_crossings(&levels, adjacency);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best_levels = levels.clone();
+        }
+    }
+
+    best_levels
+}
+
+/// Re-sorts `levels[depth]` by the median (mean, if the neighbor count is even) position of each
+/// slot's neighbors in `levels[reference_depth]`. Slots without such a neighbor keep their current
+/// position as their sort key, leaving them fixed relative to the rest.
+fn reorder_level(levels: &mut [Vec<usize>], adjacency: &[Vec<usize>], depth: usize, reference_depth: usize) {
+    let reference_position: BTreeMap<usize, usize> =
+        levels[reference_depth].iter().enumerate().map(|(position, &slot)| (slot, position)).collect();
+
+    let mut keyed: Vec<(f64, usize)> = levels[depth]
+        .iter()
+        .enumerate()
+        .map(|(position, &slot)| {
+            let mut neighbor_positions: Vec<usize> =
+                adjacency[slot].iter().filter_map(|neighbor| reference_position.get(neighbor).copied()).collect();
+
+            if neighbor_positions.is_empty() {
+                return (position as f64, slot);
+            }
+
+            neighbor_positions.sort_unstable();
+
+            let key = if neighbor_positions.len() % 2 == 1 {
+                neighbor_positions[neighbor_positions.len() / 2] as f64
+            } else {
+                neighbor_positions.iter().sum::<usize>() as f64 / neighbor_positions.len() as f64
+            };
+
+            (key, slot)
         })
+        .collect();
+
+    keyed.sort_by(|left, right| left.0.partial_cmp(&right.0).unwrap());
+
+    levels[depth] = keyed.into_iter().map(|(_, slot)| slot).collect();
+}
+
+fn count_total_crossings(levels: &[Vec<usize>], adjacency: &[Vec<usize>]) -> usize {
+    (0..levels.len().saturating_sub(1)).map(|depth| count_crossings_between(levels, adjacency, depth, depth + 1)).sum()
+}
+
+/// Counts crossings between adjacent levels by listing edges in order of the upper level's
+/// positions and counting inversions in the sequence of lower-level positions.
+fn count_crossings_between(levels: &[Vec<usize>], adjacency: &[Vec<usize>], upper_depth: usize, lower_depth: usize) -> usize {
+    let lower_position: BTreeMap<usize, usize> =
+        levels[lower_depth].iter().enumerate().map(|(position, &slot)| (slot, position)).collect();
+
+    let mut lower_positions_in_upper_order: Vec<usize> = Vec::new();
+    for &slot in &levels[upper_depth] {
+        let mut neighbor_positions: Vec<usize> =
+            adjacency[slot].iter().filter_map(|neighbor| lower_position.get(neighbor).copied()).collect();
+        neighbor_positions.sort_unstable();
+        lower_positions_in_upper_order.extend(neighbor_positions);
+    }
+
+    count_inversions(&mut lower_positions_in_upper_order)
+}
+
+/// Counts inversions in `values` via merge sort, i.e. pairs `(i, j)` with `i < j` and
+/// `values[i] > values[j]`. Used to count edge crossings between two adjacent layers in O(E log E).
+fn count_inversions(values: &mut [usize]) -> usize {
+    let len = values.len();
+    if len <= 1 {
+        return 0;
+    }
+
+    let mid = len / 2;
+    let mut left = values[..mid].to_vec();
+    let mut right = values[mid..].to_vec();
+
+    let mut inversions = count_inversions(&mut left) + count_inversions(&mut right);
+
+    let (mut i, mut j, mut k) = (0usize, 0usize, 0usize);
+    while i < left.len() && j < right.len() {
+        if left[i] <= right[j] {
+            values[k] = left[i];
+            i += 1;
+        } else {
+            values[k] = right[j];
+            j += 1;
+            inversions += left.len() - i;
+        }
+        k += 1;
     }
+    while i < left.len() {
+        values[k] = left[i];
+        i += 1;
+        k += 1;
+    }
+    while j < right.len() {
+        values[k] = right[j];
+        j += 1;
+        k += 1;
+    }
+
+    inversions
 }
 
 #[cfg(test)]
@@ -252,4 +757,173 @@ mod test {
 
         assert_eq!(sort_order.depths, vec![0, 1, 2, 3, 3]);
     }
+
+    #[test]
+    fn dsl_simple_add() {
+        const CONTENT: &str = "direction LR\na[Input 0] --> c{Add}\nb[Bias] --> c\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+
+        assert_eq!(graph.node_size(), 3usize);
+        assert_eq!(graph.node(0).name, "Input 0");
+        assert_eq!(graph.node(0).op, None);
+        assert_eq!(graph.node(1).name, "Add");
+        assert_eq!(graph.node(1).op, Some("Add".to_string()));
+    }
+
+    #[test]
+    fn dsl_auto_creates_unlabeled_nodes() {
+        const CONTENT: &str = "a[Input] --> b\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+
+        assert_eq!(graph.node_size(), 2usize);
+        assert_eq!(graph.node(1).id, "b");
+        assert_eq!(graph.node(1).name, "b");
+    }
+
+    #[test]
+    fn dsl_reports_line_and_column_on_syntax_error() {
+        const CONTENT: &str = "a[Input] --> b\nc[Bad] - d\n";
+
+        let err = Graph::from_str(CONTENT).expect_err("Expected a parse error");
+
+        match err {
+            GraphError::ParseError { message } => {
+                assert!(message.contains("line 2"), "message was: {message}");
+            }
+            other => panic!("Expected ParseError, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn crossing_minimization_untangles_simple_case() {
+        // a --> m2, b --> m1, m1 --> out, m2 --> out: with the initial insertion order
+        // [a, b] / [m1, m2] the two middle-level edges cross; an order exists with none.
+        let nodes = vec![
+            Node { id: "a".into(), name: "a".into(), op: None },
+            Node { id: "b".into(), name: "b".into(), op: None },
+            Node { id: "m1".into(), name: "m1".into(), op: None },
+            Node { id: "m2".into(), name: "m2".into(), op: None },
+            Node { id: "out".into(), name: "out".into(), op: None },
+        ];
+
+        let mut from_connections = vec![Vec::new(); nodes.len()];
+        let mut to_connections = vec![Vec::new(); nodes.len()];
+
+        for &(from_index, to_index) in &[(0usize, 3usize), (1, 2), (2, 4), (3, 4)] {
+            let source_edge_index = from_connections[from_index].len();
+            from_connections[from_index].push(Connection { from_index, to_index, source_edge_index });
+            to_connections[to_index].push(Connection { from_index, to_index, source_edge_index });
+        }
+
+        let graph = Graph {
+            nodes,
+            from_connections,
+            to_connections,
+            direction: Direction::LeftRight,
+        };
+
+        let sort_order = graph.reverse_topological_sort().unwrap();
+
+        let position_of = |node_index: usize| -> (usize, usize) {
+            let i = sort_order.order_indices.iter().position(|&n| n == node_index).unwrap();
+            (sort_order.depths[i], sort_order.index_at_depth[i])
+        };
+
+        let mut crossings = 0usize;
+        let edges: Vec<(usize, usize)> = vec![(0, 3), (1, 2), (2, 4), (3, 4)];
+        for i in 0..edges.len() {
+            for j in (i + 1)..edges.len() {
+                let (a_upper, a_lower) = (position_of(edges[i].0), position_of(edges[i].1));
+                let (b_upper, b_lower) = (position_of(edges[j].0), position_of(edges[j].1));
+
+                if a_upper.0 != b_upper.0 || a_lower.0 != b_lower.0 {
+                    continue; // not between the same pair of adjacent levels
+                }
+
+                let (au, al) = (a_upper.1, a_lower.1);
+                let (bu, bl) = (b_upper.1, b_lower.1);
+                if (au < bu && al > bl) || (au > bu && al < bl) {
+                    crossings += 1;
+                }
+            }
+        }
+
+        assert_eq!(crossings, 0);
+    }
+
+    #[test]
+    fn long_edge_gets_routed_through_a_virtual_chain() {
+        // a --> mid --> out, plus a --> out directly: the direct edge spans two depth levels
+        // and should be split into one virtual slot at mid's depth.
+        const CONTENT: &str = "direction LR\na[A] --> mid{Mid}\nmid --> out[Out]\na --> out\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+        let sort_order = graph.reverse_topological_sort().unwrap();
+
+        let a_index = 0usize;
+        let out_index = 2usize;
+
+        assert_eq!(sort_order.virtual_chains.len(), 1);
+        let chain = &sort_order.virtual_chains[0];
+        assert_eq!(chain.from_index, a_index);
+        assert_eq!(chain.to_index, out_index);
+        // "a"'s connections are [a->mid, a->out] in DSL order, so the long edge is index 1.
+        assert_eq!(chain.source_edge_index, 1);
+        assert_eq!(chain.depths, vec![1]);
+    }
+
+    #[test]
+    fn parallel_long_edges_get_distinct_virtual_chains() {
+        // Two separate "a --> out" connections both skip over "mid"; each must get its own
+        // virtual chain (and so its own rank) rather than collapsing onto a single channel.
+        const CONTENT: &str = "direction LR\na[A] --> mid{Mid}\nmid --> out[Out]\na --> out\na --> out\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+        let sort_order = graph.reverse_topological_sort().unwrap();
+
+        let a_index = 0usize;
+        let out_index = 2usize;
+
+        assert_eq!(sort_order.virtual_chains.len(), 2);
+        assert!(sort_order.virtual_chains.iter().all(|chain| chain.from_index == a_index && chain.to_index == out_index));
+
+        let mut source_edge_indices: Vec<usize> = sort_order.virtual_chains.iter().map(|chain| chain.source_edge_index).collect();
+        source_edge_indices.sort_unstable();
+        assert_eq!(source_edge_indices, vec![1, 2]);
+
+        let mut ranks: Vec<usize> = sort_order.virtual_chains.iter().map(|chain| chain.ranks[0]).collect();
+        ranks.sort_unstable();
+        assert_ne!(ranks[0], ranks[1], "parallel chains must occupy distinct ranks at mid's depth");
+    }
+
+    #[test]
+    fn cyclic_graph_is_rendered_by_breaking_a_feedback_edge() {
+        // A 3-cycle has no true source or sink; one edge must be reversed to make it layerable.
+        const CONTENT: &str = "direction LR\na[A] --> b[B]\nb --> c[C]\nc --> a\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+        let sort_order = graph.reverse_topological_sort().expect("cycles should no longer error");
+
+        assert_eq!(sort_order.reversed_edges.len(), 1);
+
+        // Every node must still land on a concrete, finite depth level.
+        assert!(sort_order.depths.iter().all(|&depth| depth < graph.node_size()));
+    }
+
+    #[test]
+    fn self_loop_does_not_block_sink_detection() {
+        // "hidden" feeds back into itself (e.g. an RNN cell) and is also the graph's only sink
+        // besides "out"; a self-loop must not stop it from ever being treated as a sink.
+        const CONTENT: &str = "direction LR\nin[In] --> hidden{Hidden}\nhidden --> hidden\nin --> out[Out]\n";
+
+        let graph = Graph::from_str(CONTENT).expect("Could not parse DSL!");
+        let sort_order = graph.reverse_topological_sort().expect("self-loops should not error or panic");
+
+        let hidden_index = 1usize;
+        assert!(sort_order.reversed_edges.contains(&(hidden_index, hidden_index)));
+
+        assert!(sort_order.depths.iter().all(|&depth| depth < graph.node_size()));
+    }
 }
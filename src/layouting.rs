@@ -1,6 +1,6 @@
 use std::fmt::Write;
 
-use crate::graph::{Graph, Node, SortOrder};
+use crate::graph::{Direction, Graph, Node, SortOrder};
 
 pub struct Style {
     pub top_level_margin: i32,
@@ -80,8 +80,23 @@ pub fn to_svg(g: &Graph, sort_order: &SortOrder, style: &Style) -> String {
     let level_count = sort_order.nodes_in_level.len() as i32;
     let max_count_in_single_level = *sort_order.nodes_in_level.iter().max().expect("Ther must be a max value!") as i32;
 
-    let width = style.width_per_level() * level_count + 2 * style.margin_width;
-    let height = style.height_per_level() * max_count_in_single_level + 2 * style.margin_height;
+    // In `LeftRight`, levels advance along x and nodes within a level spread along y; in
+    // `TopDown` the two axes swap roles.
+    let (level_axis_per_level, cross_axis_per_level, level_axis_box_size) = match g.direction() {
+        Direction::LeftRight => (style.width_per_level(), style.height_per_level(), style.box_width),
+        Direction::TopDown => (style.height_per_level(), style.width_per_level(), style.box_height),
+    };
+
+    let (width, height) = match g.direction() {
+        Direction::LeftRight => (
+            style.width_per_level() * level_count + 2 * style.margin_width,
+            style.height_per_level() * max_count_in_single_level + 2 * style.margin_height,
+        ),
+        Direction::TopDown => (
+            style.width_per_level() * max_count_in_single_level + 2 * style.margin_width,
+            style.height_per_level() * level_count + 2 * style.margin_height,
+        ),
+    };
 
     let mut result = String::new();
 
@@ -122,20 +137,56 @@ pub fn to_svg(g: &Graph, sort_order: &SortOrder, style: &Style) -> String {
     )
     .unwrap();
 
+    let cross_axis_box_size = match g.direction() {
+        Direction::LeftRight => style.box_height,
+        Direction::TopDown => style.box_width,
+    };
+    let level_axis_between_boxes = match g.direction() {
+        Direction::LeftRight => style.width_between_boxes,
+        Direction::TopDown => style.height_between_boxes,
+    };
+
+    // Position along the level axis (the axis levels advance on), centered within its level slot.
+    let level_coord = |level_index: usize| -> i32 {
+        (level_count - level_index as i32 - 1) * level_axis_per_level + style.margin_height + level_axis_per_level / 2
+    };
+    // Position along the cross axis (the axis nodes within a level spread on), centered within the level.
+    let cross_coord = |index_within_level: usize, level_index: usize| -> i32 {
+        index_within_level as i32 * cross_axis_per_level
+            + style.margin_height
+            + cross_axis_per_level / 2
+            + cross_axis_per_level / 2 * (max_count_in_single_level - sort_order.nodes_in_level[level_index] as i32)
+    };
+    // The level axis is x for `LeftRight`, y for `TopDown`; the cross axis is the other one.
+    let to_xy = |level: i32, cross: i32| -> (i32, i32) {
+        match g.direction() {
+            Direction::LeftRight => (level, cross),
+            Direction::TopDown => (cross, level),
+        }
+    };
+
+    // Long edges (spanning more than one level) are routed through a chain of virtual nodes
+    // instead of a single stretched bezier, so index them by their originating connection to
+    // skip drawing those directly below and to look up each chain's intermediate coordinates.
+    // `source_edge_index` disambiguates parallel connections between the same pair of nodes.
+    let virtual_chain_for = |from_index: usize, to_index: usize, source_edge_index: usize| {
+        sort_order
+            .virtual_chains
+            .iter()
+            .find(|chain| chain.from_index == from_index && chain.to_index == to_index && chain.source_edge_index == source_edge_index)
+    };
+
     for (i, &node_index) in sort_order.order_indices.iter().enumerate() {
         let level_index = sort_order.depths[i];
         let index_within_level = sort_order.index_at_depth[i];
 
-        let x = (level_count - level_index as i32 - 1) * style.width_per_level() + style.margin_height + style.width_per_level() / 2;
-        let y = (index_within_level as i32) * style.height_per_level()
-            + style.margin_height
-            + style.height_per_level() / 2
-            + style.height_per_level() / 2 * (max_count_in_single_level - sort_order.nodes_in_level[level_index] as i32);
+        let (x, y) = to_xy(level_coord(level_index), cross_coord(index_within_level, level_index));
 
         draw_box(&mut result, g.node(node_index), style, x, y);
 
         for connection_index in 0..g.to_connections()[node_index].len() {
             let from_index = g.to_connections()[node_index][connection_index].from_index;
+            let source_edge_index = g.to_connections()[node_index][connection_index].source_edge_index;
 
             // all depths info is in sort order
             let from_index_in_sort_order = sort_order.order_indices.iter().position(|&x| x == from_index).unwrap();
@@ -143,35 +194,77 @@ pub fn to_svg(g: &Graph, sort_order: &SortOrder, style: &Style) -> String {
             let from_level_index = sort_order.depths[from_index_in_sort_order];
             let num_inputs = g.to_connections()[node_index].len() as i32;
 
-            let x_from = (level_count - from_level_index as i32 - 1) * style.width_per_level()
-                + style.margin_height
-                + style.width_per_level() / 2
-                + style.box_width / 2;
-
-            let y_from = (sort_order.index_at_depth[from_index_in_sort_order] as i32) * style.height_per_level()
-                + style.margin_height
-                + style.height_per_level() / 2
-                + style.height_per_level() / 2 * (max_count_in_single_level - sort_order.nodes_in_level[from_level_index] as i32);
-
-            let y_to = y + style.box_height / 2 - (style.box_height / (num_inputs + 1)) * (num_inputs - connection_index as i32);
-
-            let x_to = x - style.box_width / 2 - 10;
-
-            let control_point_ext = style.width_between_boxes / 4 + (from_level_index - level_index - 1) as i32 * style.width_between_boxes;
-
-            writeln!(
-                &mut result,
-                "<path d=\"M {} {} C {} {}, {} {}, {} {}\" stroke=\"black\" stroke-width=\"2\" marker-end=\"url(#arrowhead)\" fill=\"none\"/>",
-                x_from,
-                y_from,
-                x_from + control_point_ext,
-                y_from,
-                x_to - control_point_ext,
-                y_to,
-                x_to,
-                y_to
-            )
-            .unwrap();
+            let from_level = level_coord(from_level_index) + level_axis_box_size / 2;
+            let from_cross = cross_coord(sort_order.index_at_depth[from_index_in_sort_order], from_level_index);
+
+            let to_level = level_coord(level_index) - level_axis_box_size / 2 - 10;
+            let to_cross = cross_coord(index_within_level, level_index) + cross_axis_box_size / 2
+                - (cross_axis_box_size / (num_inputs + 1)) * (num_inputs - connection_index as i32);
+
+            let control_point_ext = level_axis_between_boxes / 4;
+
+            // Edges reversed to break a cycle run against the data flow's layout direction; draw
+            // them dashed so they read as feedback/recurrent connections rather than forward ones.
+            let stroke_style = if sort_order.reversed_edges.contains(&(from_index, node_index)) {
+                " stroke-dasharray=\"6,4\""
+            } else {
+                ""
+            };
+
+            let (x_from, y_from) = to_xy(from_level, from_cross);
+            let (x_to, y_to) = to_xy(to_level, to_cross);
+
+            if let Some(chain) = virtual_chain_for(from_index, node_index, source_edge_index) {
+                // Poly-bezier through each virtual node's coordinate, so the spline stays in the
+                // channel between boxes instead of cutting through them.
+                let mut path = format!("M {x_from} {y_from} ");
+                let mut previous_level = from_level;
+                let mut previous_cross = from_cross;
+
+                for (&chain_level_index, &chain_rank) in chain.depths.iter().zip(chain.ranks.iter()) {
+                    let via_level = level_coord(chain_level_index);
+                    let via_cross = cross_coord(chain_rank, chain_level_index);
+
+                    let (x_c1, y_c1) = to_xy(previous_level + control_point_ext * (via_level - previous_level).signum(), previous_cross);
+                    let (x_c2, y_c2) = to_xy(via_level + control_point_ext * (previous_level - via_level).signum(), via_cross);
+                    let (x_via, y_via) = to_xy(via_level, via_cross);
+
+                    write!(&mut path, "C {x_c1} {y_c1}, {x_c2} {y_c2}, {x_via} {y_via} ").unwrap();
+
+                    previous_level = via_level;
+                    previous_cross = via_cross;
+                }
+
+                let (x_c1, y_c1) = to_xy(previous_level + control_point_ext * (to_level - previous_level).signum(), previous_cross);
+                let (x_c2, y_c2) = to_xy(to_level + control_point_ext * (previous_level - to_level).signum(), to_cross);
+                write!(&mut path, "C {x_c1} {y_c1}, {x_c2} {y_c2}, {x_to} {y_to}").unwrap();
+
+                writeln!(
+                    &mut result,
+                    "<path d=\"{path}\" stroke=\"black\" stroke-width=\"2\"{stroke_style} marker-end=\"url(#arrowhead)\" fill=\"none\"/>"
+                )
+                .unwrap();
+            } else {
+                // `from_level_index` and `level_index` are guaranteed at most one level apart
+                // here: bigger gaps always go through a virtual chain above. A feedback edge can
+                // still make `from_level_index <= level_index`, so compute the sign explicitly
+                // rather than assuming the source is always the deeper level.
+                let sign = match (to_level - from_level).signum() {
+                    0 => 1,
+                    other => other,
+                };
+                let control_point_ext = control_point_ext * sign;
+
+                let (x_c1, y_c1) = to_xy(from_level + control_point_ext, from_cross);
+                let (x_c2, y_c2) = to_xy(to_level - control_point_ext, to_cross);
+
+                writeln!(
+                    &mut result,
+                    "<path d=\"M {} {} C {} {}, {} {}, {} {}\" stroke=\"black\" stroke-width=\"2\"{stroke_style} marker-end=\"url(#arrowhead)\" fill=\"none\"/>",
+                    x_from, y_from, x_c1, y_c1, x_c2, y_c2, x_to, y_to
+                )
+                .unwrap();
+            }
         }
     }
 
@@ -179,3 +272,89 @@ pub fn to_svg(g: &Graph, sort_order: &SortOrder, style: &Style) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::Graph;
+
+    fn svg_attr<'a>(svg: &'a str, attr: &str) -> &'a str {
+        let start = svg.find(&format!("{attr}=\"")).unwrap() + attr.len() + 2;
+        &svg[start..svg[start..].find('"').unwrap() + start]
+    }
+
+    #[test]
+    fn top_down_swaps_width_and_height_versus_left_right() {
+        const CONTENT_LR: &str = "direction LR\na[A] --> b[B]\n";
+        const CONTENT_TD: &str = "direction TD\na[A] --> b[B]\n";
+
+        let lr_svg = to_svg(&Graph::from_str(CONTENT_LR).unwrap(), &Graph::from_str(CONTENT_LR).unwrap().reverse_topological_sort().unwrap(), &DEFAULT_STYLE);
+        let td_svg = to_svg(&Graph::from_str(CONTENT_TD).unwrap(), &Graph::from_str(CONTENT_TD).unwrap().reverse_topological_sort().unwrap(), &DEFAULT_STYLE);
+
+        // Two levels, one node per level. Boxes aren't square, so swapping axes doesn't just
+        // swap the two totals outright; what must hold is that each canvas dimension is still
+        // built from the matching per-level size (width always from width_per_level, height
+        // always from height_per_level), with level_count and max_count_in_single_level trading
+        // places between them.
+        let level_count = 2;
+        let max_count_in_single_level = 1;
+        let expected_lr_width = DEFAULT_STYLE.width_per_level() * level_count + 2 * DEFAULT_STYLE.margin_width;
+        let expected_lr_height = DEFAULT_STYLE.height_per_level() * max_count_in_single_level + 2 * DEFAULT_STYLE.margin_height;
+        let expected_td_width = DEFAULT_STYLE.width_per_level() * max_count_in_single_level + 2 * DEFAULT_STYLE.margin_width;
+        let expected_td_height = DEFAULT_STYLE.height_per_level() * level_count + 2 * DEFAULT_STYLE.margin_height;
+
+        assert_eq!(svg_attr(&lr_svg, "width").parse(), Ok(expected_lr_width));
+        assert_eq!(svg_attr(&lr_svg, "height").parse(), Ok(expected_lr_height));
+        assert_eq!(svg_attr(&td_svg, "width").parse(), Ok(expected_td_width));
+        assert_eq!(svg_attr(&td_svg, "height").parse(), Ok(expected_td_height));
+    }
+
+    #[test]
+    fn virtual_nodes_are_never_drawn_as_boxes() {
+        // "a" skips straight to "out", two levels past "mid": one virtual node is inserted at
+        // mid's depth, but it must never get its own <rect>.
+        const CONTENT: &str = "direction LR\na[A] --> mid{Mid}\nmid --> out[Out]\na --> out\n";
+
+        let graph = Graph::from_str(CONTENT).unwrap();
+        let sort_order = graph.reverse_topological_sort().unwrap();
+        assert_eq!(sort_order.virtual_chains.len(), 1);
+
+        let svg = to_svg(&graph, &sort_order, &DEFAULT_STYLE);
+
+        let box_rect_count = svg.matches("fill=\"none\" stroke=\"black\"").count();
+        assert_eq!(box_rect_count, graph.node_size());
+    }
+
+    #[test]
+    fn parallel_long_edges_are_routed_through_separate_paths() {
+        // Two "a --> out" connections both skip over "mid"; each needs its own poly-bezier path
+        // rather than both collapsing onto the same virtual chain's coordinates.
+        const CONTENT: &str = "direction LR\na[A] --> mid{Mid}\nmid --> out[Out]\na --> out\na --> out\n";
+
+        let graph = Graph::from_str(CONTENT).unwrap();
+        let sort_order = graph.reverse_topological_sort().unwrap();
+        assert_eq!(sort_order.virtual_chains.len(), 2);
+
+        let svg = to_svg(&graph, &sort_order, &DEFAULT_STYLE);
+
+        // Every connection draws its own <path>: one a->mid, one mid->out, and two a->out ones.
+        assert_eq!(svg.matches("<path").count(), 4);
+
+        // The two long a->out edges are the only poly-beziers (two "C" segments: through mid,
+        // then on to out); the direct a->mid and mid->out edges only have one each. The via
+        // point at mid's rank is the endpoint of the first cubic segment (its third coordinate
+        // pair), not the control point right after the start, which is the same for both since
+        // they share the same source node.
+        let via_points: Vec<&str> = svg
+            .lines()
+            .filter(|line| line.starts_with("<path") && line.matches("C ").count() == 2)
+            .map(|line| line.split("C ").nth(1).unwrap().split(", ").nth(2).unwrap())
+            .collect();
+
+        assert_eq!(via_points.len(), 2);
+        assert_ne!(
+            via_points[0], via_points[1],
+            "parallel a->out chains must pass through distinct coordinates at mid's depth, not overlap"
+        );
+    }
+}